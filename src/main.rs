@@ -1,3 +1,4 @@
+mod pty;
 mod runner;
 mod types;
 
@@ -14,13 +15,14 @@ pub mod coderun {
 use crate::coderun::code_runner_server::CodeRunnerServer;
 use crate::coderun::command_request::Command;
 use crate::coderun::{
-    CommandRequest, CommandResponse, GetFileResponse, PutFileResponse, RunCodeResponse, RunStatus,
+    CommandRequest, CommandResponse, GetFileResponse, InteractiveResponse, OutputStream,
+    PutFileResponse, RecordingFrame, RecordingResponse, RunCodeResponse, RunStatus, StepResponse,
     command_response,
 };
 use crate::runner::Runner;
 use crate::types::Limit;
 use std::error::Error;
-use std::io::ErrorKind;
+use std::io::{ErrorKind, Read};
 use std::pin::Pin;
 
 const CODE_DIR: &str = "/var/tmp/code-runner";
@@ -68,106 +70,93 @@ impl CodeRunner for MyCodeRunner {
             let session_id = (0..20)
                 .map(|_| fastrand::alphanumeric())
                 .collect::<String>();
-            let mut runner = Runner::new(format!("{}/{}", CODE_DIR, session_id));
-
-            while let Some(result) = in_stream.next().await {
-                match result {
-                    Ok(v) => {
-                        let command = v
-                            .command
-                            .ok_or_else(|| {
-                                Status::invalid_argument("CommandRequest must contain a command")
-                            })
-                            .expect("CommandRequest must contain a command");
-                        // coderun::command_request::Command
-                        match command {
-                            Command::Put(put) => {
-                                let file_path = put.filename;
-                                let content = put.content;
-
-                                if let Err(err) = runner.put_file(file_path, &content) {
-                                    tx.send(Err(Status::internal(format!(
-                                        "Failed to put file: {}",
-                                        err
-                                    ))))
-                                    .await
-                                    .unwrap();
-                                    continue;
-                                }
+            let code_dir = format!("{}/{}", CODE_DIR, session_id);
+            let mut runner = Runner::new(code_dir.clone());
+
+            // Set once an interactive session is running: carries raw chunks
+            // read off the pty master so they can be interleaved with
+            // incoming `CommandRequest`s via `select!` below.
+            let mut pty_output: Option<mpsc::Receiver<std::io::Result<Vec<u8>>>> = None;
+
+            // The most recently finished Run or interactive session's
+            // recording, if it asked for `record: true`; served on the next
+            // `Command::GetRecording` and cleared by any other command except
+            // `Command::Input` (or a rejected `Command::Configure`), so a
+            // client can't fetch a recording that belongs to a stale run.
+            let mut last_recording: Option<Vec<types::RecordingFrame>> = None;
+
+            loop {
+                let pty_chunk = async {
+                    match &mut pty_output {
+                        Some(rx) => rx.recv().await,
+                        None => std::future::pending().await,
+                    }
+                };
 
+                tokio::select! {
+                    chunk = pty_chunk => {
+                        match chunk {
+                            Some(Ok(bytes)) => {
+                                runner.record_interactive(&bytes);
                                 tx.send(Ok(CommandResponse {
-                                    response: Some(command_response::Response::Put(
-                                        PutFileResponse {
-                                            length: content.len() as u32,
-                                        },
+                                    response: Some(command_response::Response::Interactive(
+                                        InteractiveResponse { data: bytes, exited: false, exit_code: None },
                                     )),
                                     ..Default::default()
                                 }))
                                 .await
                                 .unwrap();
                             }
-                            Command::Run(run) => {
-                                let run_command = run.command;
-                                let limits = run.limits;
-                                let stdin = run.input;
-
-                                let limit = if let Some(limits) = limits {
-                                    Some(Limit {
-                                        memory: Some(limits.max_memory),
-                                        time_limit: Some(limits.max_runtime),
-                                        walltime_limit: Some(limits.max_runtime * 2),
-                                    })
-                                } else {
-                                    None
-                                };
-
-                                let output = runner.execute_program(
-                                    "/usr/bin/sh",
-                                    vec!["-c".to_string(), run_command],
-                                    limit,
-                                    stdin,
-                                );
-
+                            Some(Err(_)) | None => {
+                                pty_output = None;
+                                let status = runner.wait_interactive();
+                                let exit_code = status.ok().map(|s| s.code);
+                                if let Some(recording) = runner.take_interactive_recording() {
+                                    last_recording = Some(recording);
+                                }
                                 tx.send(Ok(CommandResponse {
-                                    response: Some(command_response::Response::Run(
-                                        RunCodeResponse {
-                                            stdout: output.stdout,
-                                            stderr: output.stderr,
-                                            status: match output.status {
-                                                types::RunStatus::Success => {
-                                                    RunStatus::Success.into()
-                                                }
-                                                types::RunStatus::TimeLimitExceeded => {
-                                                    RunStatus::TimeLimitExceeded.into()
-                                                }
-
-                                                types::RunStatus::SystemError(_) => {
-                                                    RunStatus::SystemError.into()
-                                                }
-
-                                                types::RunStatus::RuntimeError(_) => {
-                                                    RunStatus::RuntimeError.into()
-                                                }
-                                            },
-                                            runtime: output.runtime as u64,
-                                            memory: output.memory_usage as u64,
-                                            exit_code: output.exit_code,
-                                        },
+                                    response: Some(command_response::Response::Interactive(
+                                        InteractiveResponse { data: Vec::new(), exited: true, exit_code },
                                     )),
                                     ..Default::default()
                                 }))
                                 .await
                                 .unwrap();
                             }
-                            Command::Get(get) => {
-                                let file_path = get.filename;
+                        }
+                        continue;
+                    }
+                    result = in_stream.next() => {
+                        match result {
+                            None => break,
+                            Some(Ok(v)) => {
+                                let command = v
+                                    .command
+                                    .ok_or_else(|| {
+                                        Status::invalid_argument("CommandRequest must contain a command")
+                                    })
+                                    .expect("CommandRequest must contain a command");
+                                // coderun::command_request::Command
+                                match command {
+                                    Command::Put(put) => {
+                                        last_recording = None;
+                                        let file_path = put.filename;
+                                        let content = put.content;
+
+                                        if let Err(err) = runner.put_file(file_path, &content) {
+                                            tx.send(Err(Status::internal(format!(
+                                                "Failed to put file: {}",
+                                                err
+                                            ))))
+                                            .await
+                                            .unwrap();
+                                            continue;
+                                        }
 
-                                match runner.get_file(file_path) {
-                                    Ok(content) => {
                                         tx.send(Ok(CommandResponse {
-                                            response: Some(command_response::Response::Get(
-                                                GetFileResponse {
-                                                    content: content.clone(),
+                                            response: Some(command_response::Response::Put(
+                                                PutFileResponse {
+                                                    length: content.len() as u32,
                                                 },
                                             )),
                                             ..Default::default()
@@ -175,29 +164,386 @@ impl CodeRunner for MyCodeRunner {
                                         .await
                                         .unwrap();
                                     }
-                                    Err(err) => {
-                                        tx.send(Err(Status::internal(format!(
-                                            "Failed to get file: {}",
-                                            err
-                                        ))))
+                                    Command::Run(run) => {
+                                        let run_command = run.command;
+                                        let limits = run.limits;
+                                        let stdin = run.input;
+                                        let record = run.record;
+                                        last_recording = None;
+
+                                        let limit = if let Some(limits) = limits {
+                                            Some(Limit {
+                                                memory: Some(limits.max_memory),
+                                                time_limit: Some(limits.max_runtime),
+                                                walltime_limit: Some(limits.max_runtime * 2),
+                                                output_limit: limits.max_output,
+                                                max_processes: limits.max_processes,
+                                                max_open_files: limits.max_open_files,
+                                                max_file_size: limits.max_file_size,
+                                            })
+                                        } else {
+                                            None
+                                        };
+
+                                        let (chunk_tx, mut chunk_rx) =
+                                            mpsc::channel::<types::OutputChunk>(128);
+                                        let forward_tx = tx.clone();
+                                        let forward_task = tokio::spawn(async move {
+                                            while let Some(chunk) = chunk_rx.recv().await {
+                                                let (stdout, stderr) = match chunk.stream {
+                                                    types::OutputStream::Stdout => {
+                                                        (chunk.data, Vec::new())
+                                                    }
+                                                    types::OutputStream::Stderr => {
+                                                        (Vec::new(), chunk.data)
+                                                    }
+                                                };
+
+                                                if forward_tx
+                                                    .send(Ok(CommandResponse {
+                                                        response: Some(
+                                                            command_response::Response::Run(
+                                                                RunCodeResponse {
+                                                                    stdout,
+                                                                    stderr,
+                                                                    status: RunStatus::Success
+                                                                        .into(),
+                                                                    runtime: 0,
+                                                                    memory: 0,
+                                                                    exit_code: None,
+                                                                    signal: None,
+                                                                },
+                                                            ),
+                                                        ),
+                                                        ..Default::default()
+                                                    }))
+                                                    .await
+                                                    .is_err()
+                                                {
+                                                    break;
+                                                }
+                                            }
+                                        });
+
+                                        let mut output = runner
+                                            .execute_program(
+                                                "/usr/bin/sh",
+                                                vec!["-c".to_string(), run_command],
+                                                limit,
+                                                stdin,
+                                                chunk_tx,
+                                                record,
+                                            )
+                                            .await;
+                                        forward_task.await.unwrap();
+
+                                        if let Some(recording) = output.recording.take() {
+                                            last_recording = Some(recording);
+                                        }
+
+                                        tx.send(Ok(CommandResponse {
+                                            response: Some(command_response::Response::Run(
+                                                RunCodeResponse {
+                                                    stdout: Vec::new(),
+                                                    stderr: Vec::new(),
+                                                    status: match output.status {
+                                                        types::RunStatus::Success => {
+                                                            RunStatus::Success.into()
+                                                        }
+                                                        types::RunStatus::TimeLimitExceeded => {
+                                                            RunStatus::TimeLimitExceeded.into()
+                                                        }
+
+                                                        types::RunStatus::SystemError(_) => {
+                                                            RunStatus::SystemError.into()
+                                                        }
+
+                                                        types::RunStatus::RuntimeError(_) => {
+                                                            RunStatus::RuntimeError.into()
+                                                        }
+
+                                                        types::RunStatus::OutputLimitExceeded => {
+                                                            RunStatus::OutputLimitExceeded.into()
+                                                        }
+
+                                                        types::RunStatus::CompileError(_) => {
+                                                            RunStatus::CompileError.into()
+                                                        }
+
+                                                        types::RunStatus::SecurityViolation(_) => {
+                                                            RunStatus::SecurityViolation.into()
+                                                        }
+
+                                                        types::RunStatus::MemoryLimitExceeded => {
+                                                            RunStatus::MemoryLimitExceeded.into()
+                                                        }
+                                                    },
+                                                    runtime: output.runtime as u64,
+                                                    memory: output.memory_usage as u64,
+                                                    exit_code: output.exit_code,
+                                                    signal: output.signal,
+                                                },
+                                            )),
+                                            ..Default::default()
+                                        }))
+                                        .await
+                                        .unwrap();
+                                    }
+                                    Command::Interactive(interactive) => {
+                                        last_recording = None;
+                                        let limit = interactive.limits.map(|limits| Limit {
+                                            memory: Some(limits.max_memory),
+                                            time_limit: Some(limits.max_runtime),
+                                            walltime_limit: Some(limits.max_runtime * 2),
+                                            output_limit: limits.max_output,
+                                            max_processes: limits.max_processes,
+                                            max_open_files: limits.max_open_files,
+                                            max_file_size: limits.max_file_size,
+                                        });
+
+                                        match runner.start_interactive(
+                                            &interactive.program,
+                                            interactive.args,
+                                            limit,
+                                            interactive.record,
+                                        ) {
+                                            Ok(mut master) => {
+                                                let (out_tx, out_rx) = mpsc::channel(128);
+                                                pty_output = Some(out_rx);
+
+                                                tokio::task::spawn_blocking(move || {
+                                                    let mut buf = [0u8; 4096];
+                                                    loop {
+                                                        match master.read(&mut buf) {
+                                                            Ok(0) => break,
+                                                            Ok(n) => {
+                                                                if out_tx
+                                                                    .blocking_send(Ok(buf[..n].to_vec()))
+                                                                    .is_err()
+                                                                {
+                                                                    break;
+                                                                }
+                                                            }
+                                                            Err(err) => {
+                                                                let _ = out_tx.blocking_send(Err(err));
+                                                                break;
+                                                            }
+                                                        }
+                                                    }
+                                                });
+                                            }
+                                            Err(err) => {
+                                                tx.send(Err(Status::internal(format!(
+                                                    "Failed to start interactive session: {}",
+                                                    err
+                                                ))))
+                                                .await
+                                                .unwrap();
+                                            }
+                                        }
+                                    }
+                                    Command::Input(input) => {
+                                        if let Some(resize) = input.resize {
+                                            if let Err(err) = runner
+                                                .resize_interactive(resize.rows as u16, resize.cols as u16)
+                                            {
+                                                eprintln!("Failed to resize pty: {}", err);
+                                            }
+                                        }
+
+                                        if !input.data.is_empty() {
+                                            if let Err(err) = runner.write_interactive(&input.data) {
+                                                tx.send(Err(Status::internal(format!(
+                                                    "Failed to write interactive input: {}",
+                                                    err
+                                                ))))
+                                                .await
+                                                .unwrap();
+                                            }
+                                        }
+                                    }
+                                    Command::Configure(policy) => {
+                                        if runner.has_interactive_session() {
+                                            tx.send(Err(Status::failed_precondition(
+                                                "cannot reconfigure the sandbox while an interactive session is running",
+                                            )))
+                                            .await
+                                            .unwrap();
+                                            continue;
+                                        }
+
+                                        last_recording = None;
+                                        // Rebuilds the container with the requested policy; safe
+                                        // now that we know nothing is running inside the old one.
+                                        runner = Runner::with_policy(
+                                            code_dir.clone(),
+                                            types::SandboxPolicy {
+                                                banned_syscalls: if policy.banned_syscalls.is_empty()
+                                                {
+                                                    None
+                                                } else {
+                                                    Some(policy.banned_syscalls)
+                                                },
+                                                share_network: policy.share_network,
+                                            },
+                                        );
+                                    }
+                                    Command::Pipeline(pipeline) => {
+                                        last_recording = None;
+                                        let steps = pipeline
+                                            .steps
+                                            .into_iter()
+                                            .map(|step| types::PipelineStep {
+                                                command: step.command,
+                                                limit: step.limits.map(|limits| Limit {
+                                                    memory: Some(limits.max_memory),
+                                                    time_limit: Some(limits.max_runtime),
+                                                    walltime_limit: Some(limits.max_runtime * 2),
+                                                    output_limit: limits.max_output,
+                                                    max_processes: limits.max_processes,
+                                                    max_open_files: limits.max_open_files,
+                                                    max_file_size: limits.max_file_size,
+                                                }),
+                                                stdin: step.input,
+                                                is_compile: step.is_compile,
+                                            })
+                                            .collect();
+
+                                        // Each step's result is forwarded to the client as soon
+                                        // as `execute_pipeline` produces it, so a CI/judge client
+                                        // sees live per-step progress instead of one batch of
+                                        // responses after the whole pipeline finishes.
+                                        let (result_tx, mut result_rx) =
+                                            mpsc::channel::<types::RunOutput>(16);
+                                        let forward_tx = tx.clone();
+                                        let forward_task = tokio::spawn(async move {
+                                            let mut step_index: u32 = 0;
+                                            while let Some(output) = result_rx.recv().await {
+                                                let response = Ok(CommandResponse {
+                                                    response: Some(
+                                                        command_response::Response::Step(
+                                                            StepResponse {
+                                                                step_index,
+                                                                result: Some(RunCodeResponse {
+                                                                    stdout: output.stdout,
+                                                                    stderr: output.stderr,
+                                                                    status: match output.status {
+                                                                        types::RunStatus::Success => {
+                                                                            RunStatus::Success.into()
+                                                                        }
+                                                                        types::RunStatus::TimeLimitExceeded => {
+                                                                            RunStatus::TimeLimitExceeded.into()
+                                                                        }
+                                                                        types::RunStatus::SystemError(_) => {
+                                                                            RunStatus::SystemError.into()
+                                                                        }
+                                                                        types::RunStatus::RuntimeError(_) => {
+                                                                            RunStatus::RuntimeError.into()
+                                                                        }
+                                                                        types::RunStatus::OutputLimitExceeded => {
+                                                                            RunStatus::OutputLimitExceeded.into()
+                                                                        }
+                                                                        types::RunStatus::CompileError(_) => {
+                                                                            RunStatus::CompileError.into()
+                                                                        }
+                                                                        types::RunStatus::SecurityViolation(_) => {
+                                                                            RunStatus::SecurityViolation.into()
+                                                                        }
+                                                                        types::RunStatus::MemoryLimitExceeded => {
+                                                                            RunStatus::MemoryLimitExceeded.into()
+                                                                        }
+                                                                    },
+                                                                    runtime: output.runtime as u64,
+                                                                    memory: output.memory_usage as u64,
+                                                                    exit_code: output.exit_code,
+                                                                    signal: output.signal,
+                                                                }),
+                                                            },
+                                                        ),
+                                                    ),
+                                                    ..Default::default()
+                                                });
+
+                                                if forward_tx.send(response).await.is_err() {
+                                                    break;
+                                                }
+                                                step_index += 1;
+                                            }
+                                        });
+
+                                        runner.execute_pipeline(steps, result_tx).await;
+                                        forward_task.await.unwrap();
+                                    }
+                                    Command::Get(get) => {
+                                        last_recording = None;
+                                        let file_path = get.filename;
+
+                                        match runner.get_file(file_path) {
+                                            Ok(content) => {
+                                                tx.send(Ok(CommandResponse {
+                                                    response: Some(command_response::Response::Get(
+                                                        GetFileResponse {
+                                                            content: content.clone(),
+                                                        },
+                                                    )),
+                                                    ..Default::default()
+                                                }))
+                                                .await
+                                                .unwrap();
+                                            }
+                                            Err(err) => {
+                                                tx.send(Err(Status::internal(format!(
+                                                    "Failed to get file: {}",
+                                                    err
+                                                ))))
+                                                .await
+                                                .unwrap();
+                                            }
+                                        }
+                                    }
+                                    Command::GetRecording(_) => {
+                                        let frames = last_recording
+                                            .take()
+                                            .unwrap_or_default()
+                                            .into_iter()
+                                            .map(|frame| RecordingFrame {
+                                                offset_ms: frame.offset_ms,
+                                                stream: match frame.stream {
+                                                    types::OutputStream::Stdout => {
+                                                        OutputStream::Stdout.into()
+                                                    }
+                                                    types::OutputStream::Stderr => {
+                                                        OutputStream::Stderr.into()
+                                                    }
+                                                },
+                                                data: frame.data,
+                                            })
+                                            .collect();
+
+                                        tx.send(Ok(CommandResponse {
+                                            response: Some(command_response::Response::Recording(
+                                                RecordingResponse { frames },
+                                            )),
+                                            ..Default::default()
+                                        }))
                                         .await
                                         .unwrap();
                                     }
                                 }
                             }
-                        }
-                    }
-                    Err(err) => {
-                        if let Some(io_err) = match_for_io_error(&err) {
-                            if io_err.kind() == ErrorKind::BrokenPipe {
-                                eprintln!("\tclient disconnected: broken pipe");
-                                break;
-                            }
-                        }
+                            Some(Err(err)) => {
+                                if let Some(io_err) = match_for_io_error(&err) {
+                                    if io_err.kind() == ErrorKind::BrokenPipe {
+                                        eprintln!("\tclient disconnected: broken pipe");
+                                        break;
+                                    }
+                                }
 
-                        match tx.send(Err(err)).await {
-                            Ok(_) => (),
-                            Err(_err) => break, // response was dropped
+                                match tx.send(Err(err)).await {
+                                    Ok(_) => (),
+                                    Err(_err) => break, // response was dropped
+                                }
+                            }
                         }
                     }
                 }
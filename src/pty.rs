@@ -0,0 +1,66 @@
+use std::io;
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
+
+use nix::pty::{openpty, OpenptyResult};
+use nix::sys::termios::{self, SetArg};
+
+/// A pseudo-terminal pair backing an interactive session.
+///
+/// The slave side is handed to the sandboxed child as its stdin/stdout/stderr
+/// so the program believes it is talking to a real TTY; the master side stays
+/// with us so we can read the program's output and forward keystrokes to it.
+///
+/// `slave` is an `Option` because the parent must close its own copy once the
+/// child has it duped onto its stdio: the master only reports EOF once every
+/// open fd referencing the slave is closed, so holding ours open would make
+/// `read()`s on `master` block forever after the child exits.
+pub struct Pty {
+    pub master: OwnedFd,
+    pub slave: Option<OwnedFd>,
+}
+
+impl Pty {
+    pub fn open() -> io::Result<Self> {
+        let OpenptyResult { master, slave } = openpty(None, None).map_err(io::Error::from)?;
+
+        // Put the slave in raw mode so the child doesn't have to (the caller
+        // is the one doing line editing, if any).
+        if let Ok(mut attrs) = termios::tcgetattr(&slave) {
+            termios::cfmakeraw(&mut attrs);
+            let _ = termios::tcsetattr(&slave, SetArg::TCSANOW, &attrs);
+        }
+
+        Ok(Self {
+            master,
+            slave: Some(slave),
+        })
+    }
+
+    pub fn master_fd(&self) -> RawFd {
+        self.master.as_raw_fd()
+    }
+
+    /// Close the parent's copy of the slave fd. Must be called once the child
+    /// has duped the slave onto its own stdio (after `spawn()`), or the
+    /// master will never see EOF/EIO once the child exits.
+    pub fn close_slave(&mut self) {
+        self.slave = None;
+    }
+
+    /// Propagate a client-reported window size to the slave side so
+    /// full-screen programs (editors, pagers) redraw at the right size.
+    pub fn resize(&self, rows: u16, cols: u16) -> io::Result<()> {
+        let ws = libc::winsize {
+            ws_row: rows,
+            ws_col: cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+
+        let res = unsafe { libc::ioctl(self.master.as_raw_fd(), libc::TIOCSWINSZ, &ws) };
+        if res != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
@@ -1,14 +1,85 @@
 use std::fs;
 use std::io::{Read, Write};
+use std::os::fd::AsRawFd;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Instant;
 
 use hakoniwa::seccomp::{Action, Arch, Filter};
-use hakoniwa::{Container, Namespace, Rlimit, Runctl, Stdio};
+use hakoniwa::{Child, Container, Namespace, Rlimit, Runctl, Stdio};
+use tokio::sync::mpsc;
+use tokio::sync::Mutex as AsyncMutex;
 
-use crate::types::{Limit, RunOutput, RunStatus};
+use crate::pty::Pty;
+use crate::types::{
+    Limit, OutputChunk, OutputStream, PipelineStep, RecordingFrame, RunOutput, RunStatus,
+    SandboxPolicy,
+};
+
+/// The most process output read from a pipe in one go; each read is handed
+/// to the caller as its own chunk. Small enough to keep an interactive-feeling
+/// stream, large enough to avoid a syscall per byte.
+const OUTPUT_CHUNK_SIZE: usize = 8192;
 
 pub struct Runner {
     container: Container,
     path: String,
+    interactive: Option<InteractiveSession>,
+    /// The recording captured by the most recently finished interactive
+    /// session, available for a `Command::GetRecording` until it's taken.
+    interactive_recording: Option<Vec<RecordingFrame>>,
+}
+
+/// A running PTY-backed program started via [`Runner::start_interactive`].
+///
+/// The slave end of `pty` lives with the child; we keep the master end (and
+/// the child handle) here so that input can be written and output read for
+/// as long as the session stays open.
+pub struct InteractiveSession {
+    pub pty: Pty,
+    pub child: Child,
+    recorder: Option<Recorder>,
+}
+
+/// Captures timestamped stdout/stderr frames for a single `record: true`
+/// run or interactive session, so a client can later replay the exact
+/// timing of a program's output.
+///
+/// Cheaply `Clone`able so both the stdout and stderr reader tasks in
+/// [`Runner::execute_program`] can hold one and append to it concurrently.
+#[derive(Clone)]
+pub struct Recorder {
+    start: Instant,
+    frames: Arc<StdMutex<Vec<RecordingFrame>>>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            frames: Arc::new(StdMutex::new(Vec::new())),
+        }
+    }
+
+    pub fn record(&self, stream: OutputStream, data: &[u8]) {
+        let offset_ms = self.start.elapsed().as_millis() as u64;
+        self.frames.lock().unwrap().push(RecordingFrame {
+            offset_ms,
+            stream,
+            data: data.to_vec(),
+        });
+    }
+
+    /// Consume the recorder, returning every frame captured so far sorted by
+    /// `offset_ms`. Stdout and stderr are captured on separate threads, so
+    /// frames can otherwise land out of chronological order.
+    pub fn into_frames(self) -> Vec<RecordingFrame> {
+        let mut frames = Arc::try_unwrap(self.frames)
+            .map(|m| m.into_inner().unwrap())
+            .unwrap_or_else(|shared| shared.lock().unwrap().clone());
+        frames.sort_by_key(|frame| frame.offset_ms);
+        frames
+    }
 }
 
 const BANNED_SYSCALLS: &[&str] = &[
@@ -16,8 +87,18 @@ const BANNED_SYSCALLS: &[&str] = &[
     "recvfrom",
 ];
 
+/// hakoniwa reports a process killed by a signal as exit code `128 + signal`.
+const SIGNAL_EXIT_BASE: i32 = 128;
+
 impl Runner {
     pub fn new(code_path: String) -> Self {
+        Self::with_policy(code_path, SandboxPolicy::default())
+    }
+
+    /// Like [`Runner::new`], but lets the caller relax today's strict
+    /// defaults: a custom list of banned syscalls instead of `BANNED_SYSCALLS`,
+    /// and/or keeping the network namespace shared.
+    pub fn with_policy(code_path: String, policy: SandboxPolicy) -> Self {
         fs::create_dir_all(&code_path).expect("Failed to create code directory");
         let mut container = Container::new();
 
@@ -33,12 +114,25 @@ impl Runner {
         container
             .unshare(Namespace::Cgroup)
             .unshare(Namespace::Ipc)
-            .unshare(Namespace::Uts)
-            .unshare(Namespace::Network);
+            .unshare(Namespace::Uts);
 
-        BANNED_SYSCALLS.iter().for_each(|syscall| {
-            filter.add_rule(Action::Errno(libc::SIGSYS), syscall);
-        });
+        if !policy.share_network {
+            container.unshare(Namespace::Network);
+        }
+
+        // `Action::Errno` only fails the syscall with an errno and lets the
+        // process keep running, so a banned syscall wouldn't actually
+        // terminate anything. `Action::KillProcess` kills the whole process
+        // with SIGSYS, which is what `execute_program`'s exit-code
+        // classification (`SIGNAL_EXIT_BASE + libc::SIGSYS`) expects.
+        match &policy.banned_syscalls {
+            Some(banned) => banned.iter().for_each(|syscall| {
+                filter.add_rule(Action::KillProcess, syscall);
+            }),
+            None => BANNED_SYSCALLS.iter().for_each(|syscall| {
+                filter.add_rule(Action::KillProcess, syscall);
+            }),
+        }
 
         container.rootfs("/").expect("unable to mount root fs");
         container.seccomp_filter(filter);
@@ -50,6 +144,8 @@ impl Runner {
         Self {
             container,
             path: code_path.to_string(),
+            interactive: None,
+            interactive_recording: None,
         }
     }
 
@@ -61,28 +157,58 @@ impl Runner {
         Ok(())
     }
 
-    pub fn execute_program(
+    /// Run `program` to completion, streaming stdout/stderr to `chunk_tx` as
+    /// it's produced. The output is also accumulated into the returned
+    /// `RunOutput`'s `stdout`/`stderr`, so the memory it takes is bounded only
+    /// when `limit.output_limit` is set.
+    ///
+    /// If `limit.output_limit` is set and the combined stdout+stderr exceeds
+    /// it, the process is killed and the returned status is
+    /// `RunStatus::OutputLimitExceeded`.
+    ///
+    /// When `record` is `true`, every chunk is also timestamped and kept for
+    /// the returned `RunOutput::recording`.
+    pub async fn execute_program(
         &mut self,
         program: &str,
         args: Vec<String>,
         limit: Option<Limit>,
         stdin: Option<Vec<u8>>,
+        chunk_tx: mpsc::Sender<OutputChunk>,
+        record: bool,
     ) -> RunOutput {
-        let walltime: Option<u64>;
+        let recorder = record.then(Recorder::new);
+        let mut walltime: Option<u64> = None;
+        let mut output_limit: Option<u64> = None;
+        let mut memory_limit: Option<u64> = None;
         if let Some(limit) = limit {
             if let Some(time_limit) = limit.time_limit {
                 self.container
                     .setrlimit(Rlimit::Cpu, time_limit, time_limit);
             }
 
-            if let Some(memory_limit) = limit.memory {
+            if let Some(limit) = limit.memory {
+                self.container.setrlimit(Rlimit::As, limit, limit);
+                memory_limit = Some(limit);
+            }
+
+            if let Some(max_processes) = limit.max_processes {
                 self.container
-                    .setrlimit(Rlimit::As, memory_limit, memory_limit);
+                    .setrlimit(Rlimit::Nproc, max_processes, max_processes);
+            }
+
+            if let Some(max_open_files) = limit.max_open_files {
+                self.container
+                    .setrlimit(Rlimit::Nofile, max_open_files, max_open_files);
+            }
+
+            if let Some(max_file_size) = limit.max_file_size {
+                self.container
+                    .setrlimit(Rlimit::Fsize, max_file_size, max_file_size);
             }
 
             walltime = limit.walltime_limit;
-        } else {
-            walltime = None;
+            output_limit = limit.output_limit;
         }
 
         let mut cmd = self.container.command(program);
@@ -93,9 +219,8 @@ impl Runner {
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
 
-        // cmd.wait_timeout(walltime);
-        if walltime.is_some() {
-            cmd.wait_timeout(walltime.unwrap());
+        if let Some(walltime) = walltime {
+            cmd.wait_timeout(walltime);
         }
 
         let mut proc = match cmd.spawn() {
@@ -105,8 +230,7 @@ impl Runner {
 
         if let Some(stdin) = stdin {
             if let Some(mut proc_stdin) = proc.stdin.take() {
-                if let Err(_) = proc_stdin.write_all(&stdin) {
-                    // return RunOutput::error("Failed to write to stdin".to_string(), None, None);
+                if proc_stdin.write_all(&stdin).is_err() {
                     eprintln!("warning: failed to write to stdin, process could be dead");
                 }
                 drop(proc_stdin);
@@ -115,28 +239,65 @@ impl Runner {
             }
         }
 
-        let output = match proc.wait_with_output() {
-            Ok(o) => o,
-            Err(_) => {
-                return RunOutput::error("Failed to wait for process".to_string(), None, None);
+        let stdout = proc.stdout.take();
+        let stderr = proc.stderr.take();
+
+        let total_bytes = Arc::new(AtomicU64::new(0));
+        let limit_exceeded = Arc::new(AtomicBool::new(false));
+        let child = Arc::new(AsyncMutex::new(proc));
+
+        let stdout_task = Self::spawn_reader(
+            stdout,
+            OutputStream::Stdout,
+            chunk_tx.clone(),
+            total_bytes.clone(),
+            output_limit,
+            limit_exceeded.clone(),
+            child.clone(),
+            recorder.clone(),
+        );
+        let stderr_task = Self::spawn_reader(
+            stderr,
+            OutputStream::Stderr,
+            chunk_tx,
+            total_bytes,
+            output_limit,
+            limit_exceeded.clone(),
+            child.clone(),
+            recorder.clone(),
+        );
+
+        let (stdout_buf, stderr_buf) = tokio::join!(stdout_task, stderr_task);
+        let stdout_buf = stdout_buf.unwrap_or_default();
+        let stderr_buf = stderr_buf.unwrap_or_default();
+
+        let output_status = {
+            let mut proc = child.lock().await;
+            match proc.wait() {
+                Ok(status) => status,
+                Err(_) => {
+                    return RunOutput::error(
+                        "Failed to wait for process".to_string(),
+                        Some(stderr_buf),
+                        Some(stdout_buf),
+                    );
+                }
             }
         };
 
-        let output_status = output.status.clone();
-
-        let resource = match output.status.rusage {
+        let resource = match output_status.rusage {
             Some(r) => r,
             None => {
                 eprintln!("Failed to get resource usage: {}", output_status.reason);
                 return RunOutput::error(
                     "Failed to get resource usage".to_string(),
-                    Some(output.stderr),
-                    Some(output.stdout),
+                    Some(stderr_buf),
+                    Some(stdout_buf),
                 );
             }
         };
 
-        let proc_resource = match output.status.proc_pid_status {
+        let proc_resource = match output_status.proc_pid_status {
             Some(r) => r,
             None => {
                 eprintln!(
@@ -145,27 +306,305 @@ impl Runner {
                 );
                 return RunOutput::error(
                     "Failed to get process resource usage".to_string(),
-                    Some(output.stderr),
-                    Some(output.stdout),
+                    Some(stderr_buf),
+                    Some(stdout_buf),
                 );
             }
         };
 
-        // output.status
-        let status = match output_status.code {
-            0 => RunStatus::Success,
-            137 | 152 => RunStatus::TimeLimitExceeded,
-            // 125 => RunStatus::SecurityViolation,
-            _ => RunStatus::RuntimeError(output_status.reason),
+        // hakoniwa reports a process killed by a signal as exit code
+        // `128 + signal`, mirroring the shell convention.
+        let signal = (output_status.code > SIGNAL_EXIT_BASE)
+            .then(|| output_status.code - SIGNAL_EXIT_BASE);
+
+        let exceeded_memory = memory_limit
+            .is_some_and(|limit| (proc_resource.vmrss as u64).saturating_mul(1024) > limit);
+
+        let status = if limit_exceeded.load(Ordering::SeqCst) {
+            RunStatus::OutputLimitExceeded
+        } else if exceeded_memory {
+            RunStatus::MemoryLimitExceeded
+        } else {
+            match output_status.code {
+                0 => RunStatus::Success,
+                137 | 152 => RunStatus::TimeLimitExceeded,
+                code if code == SIGNAL_EXIT_BASE + libc::SIGSYS => {
+                    RunStatus::SecurityViolation(output_status.reason)
+                }
+                code if code == SIGNAL_EXIT_BASE + libc::SIGSEGV => {
+                    RunStatus::RuntimeError("segmentation fault (SIGSEGV)".to_string())
+                }
+                code if code == SIGNAL_EXIT_BASE + libc::SIGFPE => {
+                    RunStatus::RuntimeError("floating point exception (SIGFPE)".to_string())
+                }
+                code if code == SIGNAL_EXIT_BASE + libc::SIGABRT => {
+                    RunStatus::RuntimeError("aborted (SIGABRT)".to_string())
+                }
+                _ => RunStatus::RuntimeError(output_status.reason),
+            }
         };
 
         RunOutput {
-            stdout: output.stdout,
-            stderr: output.stderr,
+            stdout: stdout_buf,
+            stderr: stderr_buf,
             runtime: resource.user_time.as_millis() + resource.system_time.as_millis(),
             memory_usage: proc_resource.vmrss as i64,
             status,
             exit_code: Some(output_status.code),
+            signal,
+            recording: recorder.map(Recorder::into_frames),
+        }
+    }
+
+    /// Drain a child's stdout/stderr pipe on a blocking thread, forwarding
+    /// each chunk read to `chunk_tx` and accumulating it into the buffer this
+    /// returns once the pipe closes. Kills `child` and stops reading as soon
+    /// as the combined byte count crosses `output_limit`. When `recorder` is
+    /// set, each chunk is also appended to it with a timestamp.
+    fn spawn_reader<R>(
+        pipe: Option<R>,
+        stream: OutputStream,
+        chunk_tx: mpsc::Sender<OutputChunk>,
+        total_bytes: Arc<AtomicU64>,
+        output_limit: Option<u64>,
+        limit_exceeded: Arc<AtomicBool>,
+        child: Arc<AsyncMutex<Child>>,
+        recorder: Option<Recorder>,
+    ) -> tokio::task::JoinHandle<Vec<u8>>
+    where
+        R: Read + Send + 'static,
+    {
+        tokio::task::spawn_blocking(move || {
+            let mut buf = Vec::new();
+            let Some(mut pipe) = pipe else {
+                return buf;
+            };
+
+            let mut chunk = [0u8; OUTPUT_CHUNK_SIZE];
+            loop {
+                let n = match pipe.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(n) => n,
+                    Err(_) => break,
+                };
+
+                buf.extend_from_slice(&chunk[..n]);
+                if let Some(recorder) = &recorder {
+                    recorder.record(stream, &chunk[..n]);
+                }
+                if chunk_tx
+                    .blocking_send(OutputChunk {
+                        stream,
+                        data: chunk[..n].to_vec(),
+                    })
+                    .is_err()
+                {
+                    break;
+                }
+
+                let total = total_bytes.fetch_add(n as u64, Ordering::SeqCst) + n as u64;
+                if let Some(limit) = output_limit {
+                    if total > limit {
+                        limit_exceeded.store(true, Ordering::SeqCst);
+                        let _ = child.blocking_lock().kill();
+                        break;
+                    }
+                }
+            }
+
+            buf
+        })
+    }
+
+    /// Run `steps` in order in the same `/box` directory, stopping at the
+    /// first one that doesn't succeed.
+    ///
+    /// Each step's `RunOutput` is sent on `result_tx` as soon as that step
+    /// finishes, rather than collected and returned once the whole pipeline
+    /// is done, so a caller streaming them on to a client gets live
+    /// per-step progress instead of one aggregate response at the end. A
+    /// step marked `is_compile` that exits non-zero is reported as
+    /// `RunStatus::CompileError` instead of `RunStatus::RuntimeError` so
+    /// callers can tell a compile failure from a runtime one.
+    pub async fn execute_pipeline(
+        &mut self,
+        steps: Vec<PipelineStep>,
+        result_tx: mpsc::Sender<RunOutput>,
+    ) {
+        for step in steps {
+            // `execute_program` streams its output as it runs, but a
+            // pipeline only reports one summarized result per step, so the
+            // chunks are drained here rather than forwarded anywhere.
+            let (chunk_tx, mut chunk_rx) = mpsc::channel::<OutputChunk>(128);
+            tokio::spawn(async move { while chunk_rx.recv().await.is_some() {} });
+
+            let mut output = self
+                .execute_program(
+                    "/usr/bin/sh",
+                    vec!["-c".to_string(), step.command],
+                    step.limit,
+                    step.stdin,
+                    chunk_tx,
+                    false,
+                )
+                .await;
+
+            if step.is_compile && output.exit_code.is_some_and(|code| code != 0) {
+                if let RunStatus::RuntimeError(_) = output.status {
+                    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+                    output.status = RunStatus::CompileError(stderr);
+                }
+            }
+
+            let succeeded = matches!(output.status, RunStatus::Success);
+            if result_tx.send(output).await.is_err() || !succeeded {
+                break;
+            }
+        }
+    }
+
+    /// Start a PTY-backed interactive program and attach it to the session.
+    ///
+    /// Returns a `File` duplicating the pty master so the caller can read the
+    /// program's output from it; the child and the pty itself are kept in `self.interactive`
+    /// so a later `write_interactive`/`resize_interactive`/`wait_interactive`
+    /// call can find them.
+    ///
+    /// Fails if an interactive session is already running: overwriting
+    /// `self.interactive` would drop its `Child` without killing or
+    /// `wait()`-ing it, orphaning the process.
+    pub fn start_interactive(
+        &mut self,
+        program: &str,
+        args: Vec<String>,
+        limit: Option<Limit>,
+        record: bool,
+    ) -> std::io::Result<std::fs::File> {
+        if self.interactive.is_some() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                "an interactive session is already running",
+            ));
+        }
+
+        let mut pty = Pty::open()?;
+
+        if let Some(limit) = limit {
+            if let Some(time_limit) = limit.time_limit {
+                self.container
+                    .setrlimit(Rlimit::Cpu, time_limit, time_limit);
+            }
+            if let Some(memory_limit) = limit.memory {
+                self.container
+                    .setrlimit(Rlimit::As, memory_limit, memory_limit);
+            }
+
+            if let Some(max_processes) = limit.max_processes {
+                self.container
+                    .setrlimit(Rlimit::Nproc, max_processes, max_processes);
+            }
+
+            if let Some(max_open_files) = limit.max_open_files {
+                self.container
+                    .setrlimit(Rlimit::Nofile, max_open_files, max_open_files);
+            }
+
+            if let Some(max_file_size) = limit.max_file_size {
+                self.container
+                    .setrlimit(Rlimit::Fsize, max_file_size, max_file_size);
+            }
+        }
+
+        let slave_fd = pty
+            .slave
+            .as_ref()
+            .expect("pty slave not yet closed")
+            .as_raw_fd();
+        let mut cmd = self.container.command(program);
+        cmd.current_dir("/box")
+            .args(args)
+            .env("PATH", "/bin")
+            .env("TERM", "xterm-256color")
+            .stdin(Stdio::fd(slave_fd))
+            .stdout(Stdio::fd(slave_fd))
+            .stderr(Stdio::fd(slave_fd));
+
+        let child = cmd.spawn()?;
+        let master_reader = std::fs::File::from(pty.master.try_clone()?);
+
+        // The child has the slave duped onto its stdio now; drop our copy so
+        // the master reports EOF once the child's last fd onto the slave
+        // closes, instead of blocking forever.
+        pty.close_slave();
+
+        self.interactive = Some(InteractiveSession {
+            pty,
+            child,
+            recorder: record.then(Recorder::new),
+        });
+        Ok(master_reader)
+    }
+
+    /// Whether a PTY-backed interactive session is currently attached.
+    pub fn has_interactive_session(&self) -> bool {
+        self.interactive.is_some()
+    }
+
+    /// Append a chunk of interactive output to the current session's
+    /// recording, if one was requested via `start_interactive(.., true)`.
+    pub fn record_interactive(&mut self, data: &[u8]) {
+        if let Some(recorder) = self.interactive.as_ref().and_then(|s| s.recorder.as_ref()) {
+            recorder.record(OutputStream::Stdout, data);
+        }
+    }
+
+    /// Take the recording captured by the most recently finished interactive
+    /// session, if any. Cleared once returned.
+    pub fn take_interactive_recording(&mut self) -> Option<Vec<RecordingFrame>> {
+        self.interactive_recording.take()
+    }
+
+    pub fn write_interactive(&mut self, data: &[u8]) -> std::io::Result<()> {
+        match &mut self.interactive {
+            Some(session) => {
+                let mut master = std::fs::File::from(
+                    session
+                        .pty
+                        .master
+                        .try_clone()
+                        .map_err(std::io::Error::from)?,
+                );
+                master.write_all(data)
+            }
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                "no interactive session running",
+            )),
+        }
+    }
+
+    pub fn resize_interactive(&mut self, rows: u16, cols: u16) -> std::io::Result<()> {
+        match &self.interactive {
+            Some(session) => session.pty.resize(rows, cols),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                "no interactive session running",
+            )),
+        }
+    }
+
+    pub fn wait_interactive(&mut self) -> std::io::Result<hakoniwa::ExitStatus> {
+        match self.interactive.take() {
+            Some(mut session) => {
+                if let Some(recorder) = session.recorder.take() {
+                    self.interactive_recording = Some(recorder.into_frames());
+                }
+                session.child.wait()
+            }
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                "no interactive session running",
+            )),
         }
     }
 
@@ -182,3 +621,251 @@ impl Runner {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+
+    static TEST_ID: AtomicU64 = AtomicU64::new(0);
+
+    fn test_runner() -> Runner {
+        test_runner_with_policy(SandboxPolicy::default())
+    }
+
+    fn test_runner_with_policy(policy: SandboxPolicy) -> Runner {
+        let id = TEST_ID.fetch_add(1, AtomicOrdering::SeqCst);
+        let path = format!("/tmp/code-runner-test-{}-{}", std::process::id(), id);
+        Runner::with_policy(path, policy)
+    }
+
+    async fn drain(mut rx: mpsc::Receiver<OutputChunk>) {
+        while rx.recv().await.is_some() {}
+    }
+
+    #[tokio::test]
+    async fn output_limit_kills_the_process_and_reports_output_limit_exceeded() {
+        let mut runner = test_runner();
+        let (tx, rx) = mpsc::channel(128);
+        tokio::spawn(drain(rx));
+
+        let output = runner
+            .execute_program(
+                "/bin/sh",
+                vec!["-c".to_string(), "yes | head -c 1000000".to_string()],
+                Some(Limit {
+                    memory: None,
+                    time_limit: Some(5),
+                    walltime_limit: Some(10),
+                    output_limit: Some(1024),
+                    max_processes: None,
+                    max_open_files: None,
+                    max_file_size: None,
+                }),
+                None,
+                tx,
+                false,
+            )
+            .await;
+
+        assert!(matches!(output.status, RunStatus::OutputLimitExceeded));
+        let _ = runner.cleanup();
+    }
+
+    #[tokio::test]
+    async fn max_file_size_limit_is_enforced_via_rlimit() {
+        let mut runner = test_runner();
+        let (tx, rx) = mpsc::channel(128);
+        tokio::spawn(drain(rx));
+
+        let output = runner
+            .execute_program(
+                "/bin/sh",
+                vec![
+                    "-c".to_string(),
+                    "dd if=/dev/zero of=/box/out.bin bs=1M count=10".to_string(),
+                ],
+                Some(Limit {
+                    memory: None,
+                    time_limit: Some(5),
+                    walltime_limit: Some(10),
+                    output_limit: None,
+                    max_processes: None,
+                    max_open_files: None,
+                    max_file_size: Some(1024),
+                }),
+                None,
+                tx,
+                false,
+            )
+            .await;
+
+        assert!(!matches!(output.status, RunStatus::Success));
+        let _ = runner.cleanup();
+    }
+
+    #[tokio::test]
+    async fn sigsegv_is_reported_as_a_runtime_error_with_the_signal_set() {
+        let mut runner = test_runner();
+        let (tx, rx) = mpsc::channel(128);
+        tokio::spawn(drain(rx));
+
+        let output = runner
+            .execute_program(
+                "/bin/sh",
+                vec!["-c".to_string(), "kill -SEGV $$".to_string()],
+                None,
+                None,
+                tx,
+                false,
+            )
+            .await;
+
+        match output.status {
+            RunStatus::RuntimeError(ref reason) => {
+                assert_eq!(reason, "segmentation fault (SIGSEGV)")
+            }
+            ref other => panic!("expected RuntimeError, got {:?}", other),
+        }
+        assert_eq!(output.signal, Some(libc::SIGSEGV));
+        let _ = runner.cleanup();
+    }
+
+    #[tokio::test]
+    async fn banned_syscall_is_killed_instead_of_just_failing() {
+        // Banning `write` (instead of the default socket/mount/etc list) means
+        // even a plain `echo` gets killed by seccomp, proving `Action::KillProcess`
+        // actually terminates the process rather than just failing the syscall.
+        let mut runner = test_runner_with_policy(SandboxPolicy {
+            banned_syscalls: Some(vec!["write".to_string()]),
+            share_network: false,
+        });
+        let (tx, rx) = mpsc::channel(128);
+        tokio::spawn(drain(rx));
+
+        let output = runner
+            .execute_program(
+                "/bin/sh",
+                vec!["-c".to_string(), "echo hi".to_string()],
+                None,
+                None,
+                tx,
+                false,
+            )
+            .await;
+
+        assert!(matches!(output.status, RunStatus::SecurityViolation(_)));
+        let _ = runner.cleanup();
+    }
+
+    #[tokio::test]
+    async fn interactive_session_echoes_input_and_reports_exit() {
+        let mut runner = test_runner();
+        let master_reader = runner
+            .start_interactive(
+                "/bin/sh",
+                vec!["-c".to_string(), "read line; echo got:$line".to_string()],
+                None,
+                false,
+            )
+            .expect("failed to start interactive session");
+
+        runner
+            .write_interactive(b"hello\n")
+            .expect("failed to write to interactive session");
+
+        // Read until the master reports EOF (the child exited and the parent
+        // closed its own copy of the slave fd), bounded so a regression of
+        // the "master never sees EOF" bug fails the test instead of hanging.
+        // A plain thread rather than `spawn_blocking`: the runtime waits for
+        // its blocking tasks on shutdown, so a stuck read there would hang.
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let mut master_reader = master_reader;
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 256];
+            loop {
+                match master_reader.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                    Err(_) => break,
+                }
+            }
+            let _ = done_tx.send(buf);
+        });
+
+        let output = done_rx
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .expect("reading interactive session output timed out (master never saw EOF)");
+
+        assert!(String::from_utf8_lossy(&output).contains("got:hello"));
+
+        runner
+            .wait_interactive()
+            .expect("failed to wait for interactive child");
+        assert!(!runner.has_interactive_session());
+    }
+
+    #[tokio::test]
+    async fn pipeline_aborts_after_a_failing_compile_step() {
+        let mut runner = test_runner();
+        let (result_tx, mut result_rx) = mpsc::channel(16);
+
+        let steps = vec![
+            PipelineStep {
+                command: "exit 1".to_string(),
+                limit: None,
+                stdin: None,
+                is_compile: true,
+            },
+            PipelineStep {
+                command: "echo should-not-run".to_string(),
+                limit: None,
+                stdin: None,
+                is_compile: false,
+            },
+        ];
+
+        runner.execute_pipeline(steps, result_tx).await;
+
+        let first = result_rx
+            .recv()
+            .await
+            .expect("expected a result for the failing compile step");
+        assert!(matches!(first.status, RunStatus::CompileError(_)));
+
+        assert!(
+            result_rx.recv().await.is_none(),
+            "pipeline should have aborted instead of running the second step"
+        );
+        let _ = runner.cleanup();
+    }
+
+    #[tokio::test]
+    async fn recording_captures_timestamped_stdout_frames() {
+        let mut runner = test_runner();
+        let (tx, rx) = mpsc::channel(128);
+        tokio::spawn(drain(rx));
+
+        let output = runner
+            .execute_program(
+                "/bin/sh",
+                vec!["-c".to_string(), "echo hello".to_string()],
+                None,
+                None,
+                tx,
+                true,
+            )
+            .await;
+
+        let frames = output
+            .recording
+            .expect("recording should be present when record=true");
+        assert!(!frames.is_empty());
+        assert!(frames
+            .iter()
+            .any(|frame| frame.stream == OutputStream::Stdout
+                && String::from_utf8_lossy(&frame.data).contains("hello")));
+        let _ = runner.cleanup();
+    }
+}
@@ -4,6 +4,59 @@ pub struct Limit {
     pub memory: Option<u64>,
     pub time_limit: Option<u64>,
     pub walltime_limit: Option<u64>,
+    pub output_limit: Option<u64>,
+    pub max_processes: Option<u64>,
+    pub max_open_files: Option<u64>,
+    pub max_file_size: Option<u64>,
+}
+
+/// Per-session overrides for the sandbox's syscall filter and network
+/// isolation, on top of the hardcoded defaults in `runner::BANNED_SYSCALLS`.
+///
+/// `Default` reproduces today's strict behavior (the fixed banned-syscall
+/// list, network namespace always unshared), so existing callers that never
+/// send a policy are unaffected.
+pub struct SandboxPolicy {
+    /// Syscalls to deny with `SIGSYS`, replacing the built-in list when set.
+    pub banned_syscalls: Option<Vec<String>>,
+    /// Keep the network namespace shared with the host instead of unsharing
+    /// it, for workloads that need loopback or outbound access.
+    pub share_network: bool,
+}
+
+impl Default for SandboxPolicy {
+    fn default() -> Self {
+        Self {
+            banned_syscalls: None,
+            share_network: false,
+        }
+    }
+}
+
+/// Which pipe a streamed [`OutputChunk`] came from.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    #[serde(rename = "stdout")]
+    Stdout,
+    #[serde(rename = "stderr")]
+    Stderr,
+}
+
+/// A slice of process output produced while a `Run` is still executing,
+/// emitted incrementally rather than only once the run has finished.
+pub struct OutputChunk {
+    pub stream: OutputStream,
+    pub data: Vec<u8>,
+}
+
+/// One chunk of output captured by a [`crate::runner::Recorder`], timestamped
+/// relative to when recording began so a client can replay a run with its
+/// original timing.
+#[derive(Serialize, Debug, Clone)]
+pub struct RecordingFrame {
+    pub offset_ms: u64,
+    pub stream: OutputStream,
+    pub data: Vec<u8>,
 }
 
 #[derive(Serialize, Debug, Deserialize)]
@@ -19,6 +72,30 @@ pub enum RunStatus {
 
     #[serde(rename = "runtime_error")]
     RuntimeError(String),
+
+    #[serde(rename = "output_limit_exceeded")]
+    OutputLimitExceeded,
+
+    #[serde(rename = "compile_error")]
+    CompileError(String),
+
+    /// The sandboxed program was killed for a seccomp violation (an attempt
+    /// to make a banned syscall); carries whatever hint hakoniwa gives us
+    /// about the offending syscall.
+    #[serde(rename = "security_violation")]
+    SecurityViolation(String),
+
+    #[serde(rename = "mle")]
+    MemoryLimitExceeded,
+}
+
+/// One stage of a [`crate::runner::Runner::execute_pipeline`] run, e.g. a compile step
+/// followed by a run step sharing the same `/box` working directory.
+pub struct PipelineStep {
+    pub command: String,
+    pub limit: Option<Limit>,
+    pub stdin: Option<Vec<u8>>,
+    pub is_compile: bool,
 }
 
 #[derive(Serialize, Debug)]
@@ -29,6 +106,11 @@ pub struct RunOutput {
     pub memory_usage: i64,
     pub status: RunStatus,
     pub exit_code: Option<i32>,
+    /// The signal that terminated the process, if it didn't exit normally.
+    pub signal: Option<i32>,
+    /// Timestamped stdout/stderr frames, present when the caller set
+    /// `record: true` on the request that produced this output.
+    pub recording: Option<Vec<RecordingFrame>>,
 }
 
 impl RunOutput {
@@ -40,6 +122,8 @@ impl RunOutput {
             memory_usage: 0,
             status: RunStatus::SystemError(reason),
             exit_code: None,
+            signal: None,
+            recording: None,
         }
     }
 }